@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fs;
+
+use once_cell::sync::Lazy;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest};
+use rocket::Request;
+use serde::Deserialize;
+
+const CREDENTIALS_FILE: &str = "credentials.json";
+const RULES_FILE: &str = "access_rules.json";
+const SESSION_COOKIE: &str = "session";
+
+/// Which operation a request is attempting, checked against a path's configured permission.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// Per-path permission, as configured in `access_rules.json`.
+#[derive(Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum Permission {
+    NoAccess,
+    ReadOnly,
+    ReadWrite,
+}
+
+impl Permission {
+    fn allows(self, access: Access) -> bool {
+        match (self, access) {
+            (Permission::NoAccess, _) => false,
+            (Permission::ReadOnly, Access::Read) => true,
+            (Permission::ReadOnly, Access::Write) => false,
+            (Permission::ReadWrite, _) => true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Rule {
+    prefix: String,
+    permission: Permission,
+}
+
+/// Credential store and path rules, loaded once at first use. Reloading
+/// requires a restart, matching how the rest of the server picks up config.
+static CREDENTIALS: Lazy<HashMap<String, String>> = Lazy::new(|| {
+    fs::read_to_string(CREDENTIALS_FILE).ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+});
+
+/// Rules sorted by prefix length, longest first, so the most specific match wins.
+static RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    let mut rules: Vec<Rule> = fs::read_to_string(RULES_FILE).ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.prefix.len()));
+    rules
+});
+
+fn permission_for(path: &str) -> Permission {
+    RULES.iter()
+        .find(|rule| path == rule.prefix || path.starts_with(&format!("{}/", rule.prefix)))
+        .map(|rule| rule.permission)
+        .unwrap_or(Permission::ReadWrite)
+}
+
+/// Whether `access` against `path` is allowed under `access_rules.json`,
+/// independent of any particular request/credential. Used by front-ends
+/// (SFTP) that authenticate once per session rather than per request, but
+/// still need the same per-path permission a request would get via
+/// `authorize`.
+pub(crate) fn path_allows(path: &str, access: Access) -> bool {
+    permission_for(path).allows(access)
+}
+
+fn verify_basic(header: &str) -> Option<String> {
+    let encoded = header.strip_prefix("Basic ")?;
+    let decoded = base64::decode(encoded).ok()?;
+    let decoded = String::from_utf8(decoded).ok()?;
+    let (username, password) = decoded.split_once(':')?;
+    verify_credentials(username, password).then(|| username.to_string())
+}
+
+/// Checks a username/password pair against the same credential store used
+/// for HTTP Basic auth, so other front-ends (SFTP) can require the same
+/// login instead of going unauthenticated.
+pub(crate) fn verify_credentials(username: &str, password: &str) -> bool {
+    matches!(CREDENTIALS.get(username), Some(expected) if expected == password)
+}
+
+/// Whether the request carries a valid credential: either an `Authorization:
+/// Basic` header matching the credential store, or a previously issued
+/// signed session cookie.
+fn authenticated_user(req: &Request) -> Option<String> {
+    if let Some(username) = req.cookies().get_private(SESSION_COOKIE).map(|cookie| cookie.value().to_string()) {
+        return Some(username);
+    }
+
+    let username = req.headers().get_one("Authorization").and_then(verify_basic)?;
+    req.cookies().add_private(rocket::http::Cookie::new(SESSION_COOKIE, username.clone()));
+    Some(username)
+}
+
+pub enum Decision {
+    Allowed,
+    Unauthorized,
+    Forbidden,
+}
+
+/// Checks whether `req` may perform `access` against the path it targets.
+///
+/// `Access::Read` against a permission that already allows reading
+/// (`ReadOnly`/`ReadWrite`) is granted without requiring credentials, so a
+/// rule can mark a folder public-read. `Access::Write` always requires a
+/// valid credential first, regardless of the matched permission. Missing/
+/// invalid credentials yield `Unauthorized`; valid credentials that still
+/// don't cover `access` yield `Forbidden`.
+pub fn authorize(req: &Request, access: Access) -> Decision {
+    let path = req.uri().to_normalized().path().to_string();
+    let permission = permission_for(&path);
+
+    if access == Access::Read && permission.allows(Access::Read) {
+        return Decision::Allowed;
+    }
+
+    if authenticated_user(req).is_none() {
+        return Decision::Unauthorized;
+    }
+
+    if permission.allows(access) {
+        Decision::Allowed
+    } else {
+        Decision::Forbidden
+    }
+}
+
+pub struct AuthError;
+
+/// Request guard for typed routes (the upload endpoints) that requires write access.
+pub struct WriteAccess;
+
+impl<'a, 'r> FromRequest<'a, 'r> for WriteAccess {
+    type Error = AuthError;
+
+    fn from_request(req: &'a Request<'r>) -> request::Outcome<Self, Self::Error> {
+        match authorize(req, Access::Write) {
+            Decision::Allowed => request::Outcome::Success(WriteAccess),
+            Decision::Unauthorized => request::Outcome::Failure((Status::Unauthorized, AuthError)),
+            Decision::Forbidden => request::Outcome::Failure((Status::Forbidden, AuthError)),
+        }
+    }
+}
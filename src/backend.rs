@@ -0,0 +1,144 @@
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Collapses `.`/`..` components in `relative` without touching the
+/// filesystem, rejecting any path whose `..` count would walk back past the
+/// start (i.e. out of whatever root it gets joined onto).
+fn normalize_relative(relative: &Path) -> io::Result<PathBuf> {
+    let mut out = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => out.push(part),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    return Err(io::Error::new(io::ErrorKind::PermissionDenied, "path escapes the shared root"));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Filesystem-agnostic view of a directory entry or stat result, as returned
+/// by `Backend`. The SFTP front-end uses it; the HTTP listing does not, and
+/// builds its entries from `std::fs` directly.
+pub struct EntryInfo {
+    pub name: String,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Abstracts the filesystem operations the HTTP routes already perform
+/// (list/stat/read/write), so other front-ends can reuse the same root-jailed
+/// view of the shared home directory instead of touching `std::fs` directly.
+pub trait Backend: Send + Sync {
+    fn resolve(&self, path: &Path) -> io::Result<PathBuf>;
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<EntryInfo>>;
+    fn stat(&self, path: &Path) -> io::Result<EntryInfo>;
+    fn open_read(&self, path: &Path) -> io::Result<fs::File>;
+    fn open_write(&self, path: &Path, create: bool, append: bool, truncate: bool) -> io::Result<fs::File>;
+    fn create_dir(&self, path: &Path) -> io::Result<()>;
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+}
+
+/// `Backend` implementation rooted at `dirs::home_dir()`, the same directory
+/// the HTTP routes serve.
+pub struct HomeDirBackend {
+    root: PathBuf,
+}
+
+impl HomeDirBackend {
+    pub fn new() -> io::Result<Self> {
+        let root = dirs::home_dir().ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "cannot resolve home directory"))?;
+        Ok(HomeDirBackend { root })
+    }
+
+    fn entry_info(&self, path: &Path) -> io::Result<EntryInfo> {
+        let metadata = fs::metadata(path)?;
+        Ok(EntryInfo {
+            name: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+}
+
+impl Backend for HomeDirBackend {
+    /// Joins `path` onto the shared root and rejects anything that would
+    /// escape it (`..` components, symlinks out of the jail).
+    ///
+    /// The leaf component may not exist yet (e.g. `create_dir`, `open_write`
+    /// with `create: true`), so we can't rely on `canonicalize()` resolving
+    /// the whole path in one shot: it fails with `NotFound` in that case.
+    /// Instead we lexically normalize `relative` first, so a `..` can never
+    /// walk back past the root regardless of what exists on disk, then
+    /// canonicalize the deepest *existing* ancestor and verify it's still
+    /// under the (canonicalized) root — which also catches a symlink
+    /// anywhere in that existing prefix pointing outside the jail.
+    fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        let relative = path.strip_prefix("/").unwrap_or(path);
+        let normalized = normalize_relative(relative)?;
+        let joined = self.root.join(&normalized);
+
+        let canonical_root = self.root.canonicalize()?;
+        let mut existing: &Path = &joined;
+        while !existing.exists() {
+            existing = match existing.parent() {
+                Some(parent) => parent,
+                None => break,
+            };
+        }
+        let canonical_existing = existing.canonicalize()?;
+        if !canonical_existing.starts_with(&canonical_root) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, "path escapes the shared root"));
+        }
+
+        Ok(joined)
+    }
+
+    fn list_dir(&self, path: &Path) -> io::Result<Vec<EntryInfo>> {
+        let target = self.resolve(path)?;
+        fs::read_dir(target)?.map(|entry| {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            Ok(EntryInfo {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                is_dir: metadata.is_dir(),
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            })
+        }).collect()
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<EntryInfo> {
+        let target = self.resolve(path)?;
+        self.entry_info(&target)
+    }
+
+    fn open_read(&self, path: &Path) -> io::Result<fs::File> {
+        fs::File::open(self.resolve(path)?)
+    }
+
+    fn open_write(&self, path: &Path, create: bool, append: bool, truncate: bool) -> io::Result<fs::File> {
+        OpenOptions::new().write(true).create(create).append(append).truncate(truncate).open(self.resolve(path)?)
+    }
+
+    fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir(self.resolve(path)?)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(self.resolve(path)?)
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(self.resolve(path)?)
+    }
+}
@@ -0,0 +1,38 @@
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// Coarse file category used to pick a listing icon, derived from MIME type
+/// (or, for extensionless files, a quick binary/text content sniff).
+pub fn category_for(path: &Path, is_dir: bool) -> String {
+    if is_dir {
+        return "directory".into();
+    }
+
+    if let Some(mime) = mime_guess::from_path(path).first() {
+        return match mime.type_() {
+            mime_guess::mime::IMAGE => "image",
+            mime_guess::mime::VIDEO => "video",
+            mime_guess::mime::AUDIO => "audio",
+            mime_guess::mime::TEXT => "text",
+            _ => match mime.subtype().as_str() {
+                "zip" | "x-tar" | "gzip" | "x-7z-compressed" | "x-rar-compressed" => "archive",
+                "pdf" => "document",
+                _ => "binary",
+            },
+        }.into();
+    }
+
+    if is_text_file(path) { "text".into() } else { "binary".into() }
+}
+
+/// Sniffs the first few KB of an extensionless file to guess text vs binary:
+/// any NUL byte in the sample means binary, the same heuristic `file`/git use.
+fn is_text_file(path: &Path) -> bool {
+    let mut buf = [0u8; 8192];
+    let read = match fs::File::open(path).and_then(|mut f| f.read(&mut buf)) {
+        Ok(read) => read,
+        Err(_) => return false,
+    };
+    !buf[..read].contains(&0)
+}
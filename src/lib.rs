@@ -0,0 +1 @@
+pub mod make_html;
@@ -1,18 +1,28 @@
 #![feature(proc_macro_hygiene, decl_macro)]
-use rocket::{post, routes, Handler, Request, Response, Route, Data};
+use rocket::{post, patch, head, catch, catchers, routes, Handler, Request, Response, Route, Data};
 use rocket::http::{ContentType, Status, Method};
 use rocket::handler::Outcome;
+use rocket::request::FromRequest;
 use rocket::response::status::Custom;
 
 use rocket_contrib::serve::{StaticFiles, Options};
 use multipart::server::{Multipart, save::{SaveResult, PartialReason}};
 use chrono::{DateTime, Local};
 use itertools::Itertools;
+use serde::Serialize;
 
 use std::path::PathBuf;
-use std::io::Cursor;
-use std::fmt;
-use std::fs::DirEntry;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::fs::{DirEntry, File};
+
+mod thumbnail;
+mod template;
+mod classify;
+mod sort;
+mod resumable;
+mod backend;
+mod sftp;
+mod auth;
 
 fn resolve_post(ct: &ContentType, path: PathBuf, data: Data) -> Result<String, Custom<String>> {
     let (_, boundary) = ct.params().find(|&(k,_)| k == "boundary").ok_or(
@@ -44,53 +54,136 @@ fn resolve_post(ct: &ContentType, path: PathBuf, data: Data) -> Result<String, C
 }
 
 #[post("/", format = "multipart/form-data", data = "<data>")]
-fn post_root(ct: &ContentType, data: Data) -> Result<String, Custom<String>> {
+fn post_root(ct: &ContentType, data: Data, _auth: auth::WriteAccess) -> Result<String, Custom<String>> {
     resolve_post(ct, PathBuf::default(), data)
 }
 
 #[post("/<path..>", format = "multipart/form-data", data = "<data>")]
-fn post_other(ct: &ContentType, path: PathBuf, data: Data) -> Result<String, Custom<String>> {
+fn post_other(ct: &ContentType, path: PathBuf, data: Data, _auth: auth::WriteAccess) -> Result<String, Custom<String>> {
     resolve_post(ct, path, data)
 }
 
+struct UploadLength(u64);
 
-enum EntryType {
-    File,
-    Directory,
+impl<'a, 'r> FromRequest<'a, 'r> for UploadLength {
+    type Error = ();
+
+    fn from_request(req: &'a Request<'r>) -> rocket::Outcome<Self, Self::Error> {
+        match req.headers().get_one("Upload-Length").and_then(|v| v.parse().ok()) {
+            Some(len) => rocket::Outcome::Success(UploadLength(len)),
+            None => rocket::Outcome::Failure((Status::BadRequest, ())),
+        }
+    }
 }
 
-impl fmt::Display for EntryType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::File => write!(f, "file"),
-            Self::Directory => write!(f, "directory")
+struct UploadOffset(u64);
+
+impl<'a, 'r> FromRequest<'a, 'r> for UploadOffset {
+    type Error = ();
+
+    fn from_request(req: &'a Request<'r>) -> rocket::Outcome<Self, Self::Error> {
+        match req.headers().get_one("Upload-Offset").and_then(|v| v.parse().ok()) {
+            Some(offset) => rocket::Outcome::Success(UploadOffset(offset)),
+            None => rocket::Outcome::Failure((Status::BadRequest, ())),
         }
     }
 }
 
+/// Opens a resumable upload session for `path`, expecting `Upload-Length` total bytes.
+#[post("/resumable/<path..>")]
+fn create_resumable(path: PathBuf, length: UploadLength, _auth: auth::WriteAccess) -> Result<Custom<String>, Custom<String>> {
+    let home = dirs::home_dir().unwrap();
+    let dest_path = home.join(path);
+
+    match resumable::create_session(&home, dest_path, length.0) {
+        Ok(session_id) => Ok(Custom(Status::Created, session_id)),
+        Err(resumable::CreateError::TooLarge) => Err(Custom(Status::PayloadTooLarge, "Upload exceeds the configured size limit".into())),
+        Err(resumable::CreateError::Io(err)) => Err(Custom(Status::InternalServerError, err.to_string())),
+    }
+}
+
+/// Appends a chunk to an open session at `Upload-Offset`, completing the
+/// upload once the full length has been received.
+#[patch("/resumable/<session_id>", data = "<data>")]
+fn patch_resumable(session_id: String, offset: UploadOffset, data: Data, _auth: auth::WriteAccess) -> Result<Custom<String>, Custom<String>> {
+    match resumable::apply_chunk(&session_id, offset.0, &mut data.open()) {
+        Ok(resumable::PatchOutcome::Continue(len)) => Ok(Custom(Status::NoContent, len.to_string())),
+        Ok(resumable::PatchOutcome::Completed) => Ok(Custom(Status::Created, "Upload complete".into())),
+        Err(resumable::PatchError::NotFound) => Err(Custom(Status::NotFound, "Unknown upload session".into())),
+        Err(resumable::PatchError::OffsetMismatch(current)) => Err(Custom(Status::Conflict, current.to_string())),
+        Err(resumable::PatchError::TooLarge) => Err(Custom(Status::PayloadTooLarge, "Chunk exceeds the session's declared length".into())),
+        Err(resumable::PatchError::Io(err)) => Err(Custom(Status::InternalServerError, err.to_string())),
+    }
+}
+
+/// Reports the current `Upload-Offset` of a session so a client can resume after an interruption.
+#[head("/resumable/<session_id>")]
+fn head_resumable(session_id: String, _auth: auth::WriteAccess) -> Result<Response<'static>, Custom<String>> {
+    match resumable::session_offset(&session_id) {
+        Some(offset) => Ok(Response::build().raw_header("Upload-Offset", offset.to_string()).finalize()),
+        None => Err(Custom(Status::NotFound, "Unknown upload session".into())),
+    }
+}
+
+
+enum EntryType {
+    File,
+    Directory,
+}
+
+/// Serializable view of a directory entry, rendered into the index template.
+#[derive(Serialize)]
 struct EntryDetail {
     name: String,
-    path: PathBuf,
-    entry_type: EntryType,
+    href: String,
+    category: String,
     size: Option<u64>,
-    date: Option<DateTime<Local>>,
+    date: Option<String>,
+    thumbnail: Option<String>,
 }
 
 impl EntryDetail {
     fn new(name: String, path: PathBuf, entry_type: EntryType, size: Option<u64>, date: Option<DateTime<Local>>) -> Self {
-        EntryDetail { name, path, entry_type, size, date }
+        let home = dirs::home_dir().unwrap();
+        let href = PathBuf::from("/").join(pathdiff::diff_paths(&path, &home).unwrap()).to_str().unwrap_or_default().to_string();
+
+        let thumbnail = if matches!(entry_type, EntryType::File) && thumbnail::is_image(&path) {
+            thumbnail::ensure_thumbnail(&home, &path).ok().and_then(|thumb_path| {
+                pathdiff::diff_paths(thumb_path, home.join(thumbnail::THUMBNAIL_DIR))
+                    .map(|relpath| PathBuf::from("/").join(thumbnail::THUMBNAIL_DIR).join(relpath).to_str().unwrap_or_default().to_string())
+            })
+        } else { None };
+
+        let category = classify::category_for(&path, matches!(entry_type, EntryType::Directory));
+
+        EntryDetail {
+            name,
+            href,
+            category,
+            size,
+            date: date.map(|d| d.to_string()),
+            thumbnail,
+        }
     }
+}
 
-    fn to_html(self) -> String {
-        format!("<li><a href=\"{}\" class=\"icon icon-{}\" title=\"{}\"><span class=\"name\">{}</span><span class=\"size\">{}</span><span class=\"date\">{}</span></a></li>",
-            PathBuf::from("/").join(pathdiff::diff_paths(self.path, dirs::home_dir().unwrap()).unwrap()).to_str().unwrap_or_default(),
-            self.entry_type,
-            self.name,
-            self.name,
-            if let Some(s) = self.size { format!("{}", s) } else { "".into() },
-            if let Some(d) = self.date { format!("{}", d) } else { "".into() }
-        )
+/// Whether recursive directory sizes are enabled; off by default since
+/// summing a deep tree on every listing request can be expensive.
+fn recursive_dir_size_enabled() -> bool {
+    std::env::var("RECURSIVE_DIR_SIZE").map(|v| v == "1").unwrap_or(false)
+}
+
+fn dir_size(path: &PathBuf) -> Option<u64> {
+    let mut total = 0u64;
+    for entry in std::fs::read_dir(path).ok()?.filter_map(Result::ok) {
+        let file_type = entry.file_type().ok()?;
+        total += if file_type.is_dir() {
+            dir_size(&entry.path()).unwrap_or(0)
+        } else {
+            entry.metadata().map(|m| m.len()).unwrap_or(0)
+        };
     }
+    Some(total)
 }
 
 impl From<DirEntry> for EntryDetail {
@@ -102,41 +195,155 @@ impl From<DirEntry> for EntryDetail {
 
         let size = if let EntryType::File = entry_type {
             from.metadata().map(|x| x.len()).ok()
+        } else if recursive_dir_size_enabled() {
+            dir_size(&path)
         } else { None };
 
         let date = if let EntryType::File = entry_type {
             from.metadata().and_then(|x| x.modified()).ok().map(|x| x.into())
         } else { None };
 
-        Self { name, path, entry_type, size, date }
+        Self::new(name, path, entry_type, size, date)
+    }
+}
+
+/// A single link in the directory-listing breadcrumb trail.
+#[derive(Serialize)]
+struct Breadcrumb {
+    name: String,
+    href: String,
+}
+
+/// Builds the breadcrumb trail from `home` down to `target`.
+fn breadcrumbs_for(target: &PathBuf, home: &PathBuf) -> Vec<Breadcrumb> {
+    let mut breadcrumbs = vec![Breadcrumb { name: "/".into(), href: "/".into() }];
+
+    let mut acc = PathBuf::new();
+    for component in pathdiff::diff_paths(target, home).unwrap_or_default().components() {
+        acc.push(component);
+        breadcrumbs.push(Breadcrumb {
+            name: component.as_os_str().to_string_lossy().into_owned(),
+            href: PathBuf::from("/").join(&acc).to_str().unwrap_or_default().to_string(),
+        });
     }
+
+    breadcrumbs
 }
 
+/// Top-level context handed to the index template.
+#[derive(Serialize)]
+struct ServerInfo {
+    breadcrumbs: Vec<Breadcrumb>,
+    entries: Vec<EntryDetail>,
+}
 
 #[derive(Clone)]
 struct ServeIndex();
 
+/// Whether `path`'s extension marks it as Markdown source we should render
+/// inline instead of serving as a raw download.
+fn is_markdown(path: &PathBuf) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref(),
+        Some("md") | Some("markdown")
+    )
+}
+
+/// `?raw=1` bypasses Markdown rendering and falls through to the file download handlers.
+fn raw_requested(req: &Request) -> bool {
+    req.uri().query().map_or(false, |query| query.split('&').any(|kv| kv == "raw=1"))
+}
+
+#[derive(Clone, Copy)]
+enum SortKey {
+    Name,
+    Size,
+    Date,
+}
+
+/// Parses `?sort=` into a column and direction, e.g. `size` or `date_desc`.
+/// Unrecognized or missing values fall back to ascending natural name order.
+fn sort_params(req: &Request) -> (SortKey, bool) {
+    match req.uri().query().and_then(|query| query.split('&').find_map(|kv| kv.strip_prefix("sort="))) {
+        Some("size") => (SortKey::Size, false),
+        Some("size_desc") => (SortKey::Size, true),
+        Some("date") => (SortKey::Date, false),
+        Some("date_desc") => (SortKey::Date, true),
+        Some("name_desc") => (SortKey::Name, true),
+        _ => (SortKey::Name, false),
+    }
+}
+
+fn compare_entries(a: &DirEntry, b: &DirEntry, key: SortKey, desc: bool) -> std::cmp::Ordering {
+    let ordering = match key {
+        SortKey::Name => sort::natural_cmp(&a.file_name().to_string_lossy(), &b.file_name().to_string_lossy()),
+        SortKey::Size => a.metadata().map(|m| m.len()).unwrap_or(0).cmp(&b.metadata().map(|m| m.len()).unwrap_or(0)),
+        SortKey::Date => a.metadata().and_then(|m| m.modified()).unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+            .cmp(&b.metadata().and_then(|m| m.modified()).unwrap_or(std::time::SystemTime::UNIX_EPOCH)),
+    };
+    if desc { ordering.reverse() } else { ordering }
+}
+
+fn escape_html(input: &str) -> String {
+    input.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Renders a Markdown file to an HTML fragment, escaping any embedded raw HTML
+/// rather than passing it through verbatim.
+fn render_markdown(path: &PathBuf) -> Option<String> {
+    let source = std::fs::read_to_string(path).ok()?;
+
+    let parser = pulldown_cmark::Parser::new(&source).map(|event| match event {
+        pulldown_cmark::Event::Html(html) => pulldown_cmark::Event::Text(escape_html(&html).into()),
+        pulldown_cmark::Event::InlineHtml(html) => pulldown_cmark::Event::Text(escape_html(&html).into()),
+        other => other,
+    });
+
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    Some(html)
+}
+
 impl Handler for ServeIndex {
     fn handle<'r>(&self, req: &'r Request, data: Data) -> Outcome<'r> {
+        match auth::authorize(req, auth::Access::Read) {
+            auth::Decision::Allowed => {}
+            auth::Decision::Unauthorized => return Outcome::Failure(Status::Unauthorized),
+            auth::Decision::Forbidden => return Outcome::Failure(Status::Forbidden),
+        }
+
         let path = &req.uri().to_normalized().path()[1..].to_string();
         let path = if path.len() > 0 { PathBuf::from(path) } else { PathBuf::default() };
 
         let target = dirs::home_dir().unwrap().join(path);
 
-        if !target.is_dir() {
-            Outcome::Forward(data)
-        } else {
-            let mut html = String::new();
-            if target != dirs::home_dir().unwrap() {
-                html += &EntryDetail::new("..".into(), target.parent().unwrap().into(), EntryType::Directory, None, None).to_html()
+        if target.is_dir() {
+            let home = dirs::home_dir().unwrap();
+            let (sort_key, desc) = sort_params(req);
+            let mut entries = Vec::new();
+
+            if target != home {
+                entries.push(EntryDetail::new("..".into(), target.parent().unwrap().into(), EntryType::Directory, None, None));
             }
 
-            html += &target.read_dir().unwrap().filter_map(Result::ok).filter(|x| x.file_type().unwrap().is_dir()).sorted_by(|x, y| Ord::cmp(&x.file_name(), &y.file_name())).map(|x| EntryDetail::from(x).to_html()).join("");
-            html += &target.read_dir().unwrap().filter_map(Result::ok).filter(|x| x.file_type().unwrap().is_file()).sorted_by(|x, y| Ord::cmp(&x.file_name(), &y.file_name())).map(|x| EntryDetail::from(x).to_html()).join("");
+            entries.extend(target.read_dir().unwrap().filter_map(Result::ok).filter(|x| x.file_type().unwrap().is_dir()).sorted_by(|x, y| compare_entries(x, y, sort_key, desc)).map(EntryDetail::from));
+            entries.extend(target.read_dir().unwrap().filter_map(Result::ok).filter(|x| x.file_type().unwrap().is_file()).sorted_by(|x, y| compare_entries(x, y, sort_key, desc)).map(EntryDetail::from));
 
-            let result = share_home_server::make_html::make_html(html, target);
-            let resp = Response::build().sized_body(Cursor::new(result)).finalize();
+            let context = ServerInfo { breadcrumbs: breadcrumbs_for(&target, &home), entries };
+            let result = template::render_index(&context);
+            let resp = Response::build().header(ContentType::HTML).sized_body(Cursor::new(result)).finalize();
             Outcome::Success(resp)
+        } else if is_markdown(&target) && !raw_requested(req) {
+            match render_markdown(&target) {
+                Some(rendered) => {
+                    let result = share_home_server::make_html::make_html(rendered, target);
+                    let resp = Response::build().header(ContentType::HTML).sized_body(Cursor::new(result)).finalize();
+                    Outcome::Success(resp)
+                }
+                None => Outcome::Forward(data),
+            }
+        } else {
+            Outcome::Forward(data)
         }
     }
 }
@@ -147,12 +354,274 @@ impl Into<Vec<Route>> for ServeIndex {
     }
 }
 
+/// A single, inclusive byte range resolved against a known content length.
+struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+/// Parses a `Range: bytes=...` header value, supporting `bytes=N-M`, `bytes=N-`
+/// and the suffix form `bytes=-N`. Multiple ranges are not supported and yield `None`.
+fn parse_range(header: &str, len: u64) -> Option<ByteRange> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+
+    let last_index = len.checked_sub(1)?;
+
+    let (start, end) = spec.split_once('-')?;
+    if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        if suffix == 0 {
+            return None;
+        }
+        let start = len.saturating_sub(suffix);
+        Some(ByteRange { start, end: last_index })
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            last_index
+        } else {
+            end.parse::<u64>().ok()?.min(last_index)
+        };
+        if start > end {
+            return None;
+        }
+        Some(ByteRange { start, end })
+    }
+}
+
+/// Reads a bounded slice `[start, end]` of a file, `chunk_size` bytes at a time,
+/// so a range response never has to buffer more than one chunk in memory.
+struct ChunkedRangeReader {
+    file: File,
+    remaining: u64,
+    chunk_size: usize,
+}
+
+impl ChunkedRangeReader {
+    fn new(mut file: File, range: &ByteRange, chunk_size: usize) -> std::io::Result<Self> {
+        file.seek(SeekFrom::Start(range.start))?;
+        Ok(ChunkedRangeReader { file, remaining: range.end - range.start + 1, chunk_size })
+    }
+}
+
+impl Read for ChunkedRangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+
+        let cap = (buf.len() as u64).min(self.remaining).min(self.chunk_size as u64) as usize;
+        let read = self.file.read(&mut buf[..cap])?;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// `true` when `If-Range` still matches the file's current `Last-Modified`, meaning
+/// the cached representation the client is resuming is still valid.
+fn if_range_satisfied(if_range: &str, modified: Option<DateTime<Local>>) -> bool {
+    match modified {
+        Some(modified) => if_range.trim() == modified.to_rfc2822(),
+        None => false,
+    }
+}
+
+#[derive(Clone)]
+struct ServeRange();
+
+impl Handler for ServeRange {
+    fn handle<'r>(&self, req: &'r Request, data: Data) -> Outcome<'r> {
+        match auth::authorize(req, auth::Access::Read) {
+            auth::Decision::Allowed => {}
+            auth::Decision::Unauthorized => return Outcome::Failure(Status::Unauthorized),
+            auth::Decision::Forbidden => return Outcome::Failure(Status::Forbidden),
+        }
+
+        let path = &req.uri().to_normalized().path()[1..].to_string();
+        let path = if path.len() > 0 { PathBuf::from(path) } else { PathBuf::default() };
+
+        let target = dirs::home_dir().unwrap().join(path);
+
+        let metadata = match target.metadata() {
+            Ok(metadata) if metadata.is_file() => metadata,
+            _ => return Outcome::Forward(data),
+        };
+
+        let len = metadata.len();
+        let modified: Option<DateTime<Local>> = metadata.modified().ok().map(Into::into);
+        let content_type = target.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ContentType::from_extension)
+            .unwrap_or(ContentType::Binary);
+
+        let range = req.headers().get_one("Range").filter(|_| {
+            req.headers().get_one("If-Range")
+                .map_or(true, |if_range| if_range_satisfied(if_range, modified))
+        });
+
+        let range = match range {
+            Some(range) => range,
+            None => {
+                let file = match File::open(&target) {
+                    Ok(file) => file,
+                    Err(_) => return Outcome::Forward(data),
+                };
+
+                let mut builder = Response::build();
+                builder.header(content_type).raw_header("Accept-Ranges", "bytes").sized_body(file);
+                if let Some(modified) = modified {
+                    builder.raw_header("Last-Modified", modified.to_rfc2822());
+                }
+                return Outcome::Success(builder.finalize());
+            }
+        };
+
+        match parse_range(range, len) {
+            Some(range) if range.start < len => {
+                let file = match File::open(&target) {
+                    Ok(file) => file,
+                    Err(_) => return Outcome::Forward(data),
+                };
+
+                let reader = match ChunkedRangeReader::new(file, &range, 64 * 1024) {
+                    Ok(reader) => reader,
+                    Err(_) => return Outcome::Failure(Status::InternalServerError),
+                };
+
+                let mut builder = Response::build();
+                builder.status(Status::PartialContent)
+                    .header(content_type)
+                    .raw_header("Accept-Ranges", "bytes")
+                    .raw_header("Content-Range", format!("bytes {}-{}/{}", range.start, range.end, len))
+                    .raw_header("Content-Length", format!("{}", range.end - range.start + 1))
+                    .streamed_body(reader);
+                if let Some(modified) = modified {
+                    builder.raw_header("Last-Modified", modified.to_rfc2822());
+                }
+                Outcome::Success(builder.finalize())
+            }
+            _ => {
+                let resp = Response::build()
+                    .status(Status::RangeNotSatisfiable)
+                    .raw_header("Content-Range", format!("bytes */{}", len))
+                    .finalize();
+                Outcome::Success(resp)
+            }
+        }
+    }
+}
+
+impl Into<Vec<Route>> for ServeRange {
+    fn into(self) -> Vec<Route> {
+        vec![Route::ranked(5, Method::Get, "/", self.clone()), Route::ranked(5, Method::Get, "/<path..>", self)]
+    }
+}
+
+/// Serves cached thumbnails out of `thumbnail::THUMBNAIL_DIR`, gated by the
+/// same read-access check as `ServeIndex`/`ServeRange` so a thumbnail can't
+/// leak a preview of an image the access rules would otherwise hide.
+#[derive(Clone)]
+struct ServeThumbnail();
+
+impl Handler for ServeThumbnail {
+    fn handle<'r>(&self, req: &'r Request, data: Data) -> Outcome<'r> {
+        match auth::authorize(req, auth::Access::Read) {
+            auth::Decision::Allowed => {}
+            auth::Decision::Unauthorized => return Outcome::Failure(Status::Unauthorized),
+            auth::Decision::Forbidden => return Outcome::Failure(Status::Forbidden),
+        }
+
+        let prefix = format!("/{}/", thumbnail::THUMBNAIL_DIR);
+        let path = req.uri().to_normalized().path().strip_prefix(prefix.as_str()).unwrap_or_default();
+
+        // The check above saw the `/.thumbnails/...` URI; the rules that hide
+        // an image are written against the image's own path, so check that too.
+        if !auth::path_allows(&format!("/{}", path), auth::Access::Read) {
+            return Outcome::Failure(Status::Forbidden);
+        }
+
+        let target = dirs::home_dir().unwrap().join(thumbnail::THUMBNAIL_DIR).join(PathBuf::from(path));
+
+        let file = match File::open(&target) {
+            Ok(file) if target.is_file() => file,
+            _ => return Outcome::Forward(data),
+        };
+
+        let content_type = target.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ContentType::from_extension)
+            .unwrap_or(ContentType::Binary);
+
+        let resp = Response::build().header(content_type).sized_body(file).finalize();
+        Outcome::Success(resp)
+    }
+}
+
+impl Into<Vec<Route>> for ServeThumbnail {
+    fn into(self) -> Vec<Route> {
+        vec![Route::ranked(1, Method::Get, "/<path..>", self)]
+    }
+}
+
+/// Wraps a `StaticFiles` mount with the same read-access check as
+/// `ServeIndex`/`ServeRange`, so the raw file/dotfile server can't be reached
+/// directly regardless of route rank ordering.
+#[derive(Clone)]
+struct GuardedStaticFiles {
+    inner: StaticFiles,
+    rank: isize,
+}
+
+impl GuardedStaticFiles {
+    fn new(inner: StaticFiles, rank: isize) -> Self {
+        GuardedStaticFiles { inner, rank }
+    }
+}
+
+impl Handler for GuardedStaticFiles {
+    fn handle<'r>(&self, req: &'r Request, data: Data) -> Outcome<'r> {
+        match auth::authorize(req, auth::Access::Read) {
+            auth::Decision::Allowed => {}
+            auth::Decision::Unauthorized => return Outcome::Failure(Status::Unauthorized),
+            auth::Decision::Forbidden => return Outcome::Failure(Status::Forbidden),
+        }
+
+        self.inner.handle(req, data)
+    }
+}
+
+impl Into<Vec<Route>> for GuardedStaticFiles {
+    fn into(self) -> Vec<Route> {
+        vec![Route::ranked(self.rank, Method::Get, "/<path..>", self)]
+    }
+}
+
+/// Adds the `WWW-Authenticate` challenge Rocket's default 401 response lacks.
+#[catch(401)]
+fn unauthorized() -> Response<'static> {
+    Response::build()
+        .status(Status::Unauthorized)
+        .raw_header("WWW-Authenticate", "Basic realm=\"share_home_server\"")
+        .finalize()
+}
+
 fn main() {
+    let home = dirs::home_dir().expect("Error: cannot get HOME dir");
+    std::fs::create_dir_all(home.join(thumbnail::THUMBNAIL_DIR)).expect("Error: cannot create thumbnail cache dir");
+
+    sftp::spawn("0.0.0.0:2222");
+
     rocket::ignite()
         .mount("/", ServeIndex())
-        .mount("/", 
-            StaticFiles::new(dirs::home_dir().expect("Error: cannot get HOME dir"), Options::DotFiles).rank(10)
+        .mount("/", ServeRange())
+        .mount(&format!("/{}", thumbnail::THUMBNAIL_DIR), ServeThumbnail())
+        .mount("/",
+            GuardedStaticFiles::new(StaticFiles::new(home, Options::DotFiles), 10)
         )
-        .mount("/", routes![post_root, post_other])
+        .mount("/", routes![post_root, post_other, create_resumable, patch_resumable, head_resumable])
+        .register(catchers![unauthorized])
         .launch();
 }
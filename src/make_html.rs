@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; }
+ul { list-style: none; padding: 0; }
+li { display: flex; align-items: center; padding: 0.25em 0; border-bottom: 1px solid #eee; }
+.name { flex: 1; }
+.size, .date { color: #666; margin-left: 1em; }
+img { max-height: 48px; margin-right: 0.5em; }
+"#;
+
+fn escape_html(input: &str) -> String {
+    input.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Wraps a document `body` fragment (a rendered Markdown page) in a complete
+/// HTML page. Directory listings do not go through here; they are rendered
+/// from the index template.
+pub fn make_html(body: String, target: PathBuf) -> String {
+    let title = target.file_name().and_then(|name| name.to_str()).unwrap_or("/");
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head><body>{}</body></html>",
+        escape_html(title), STYLE, body
+    )
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use uuid::Uuid;
+
+const SESSION_DIR: &str = ".uploads";
+
+struct Session {
+    temp_path: PathBuf,
+    dest_path: PathBuf,
+    total: u64,
+}
+
+static SESSIONS: Lazy<Mutex<HashMap<String, Session>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Ceiling on a single upload's total size; configurable so an operator can
+/// tighten it below the filesystem's actual free space.
+pub fn max_upload_size() -> u64 {
+    std::env::var("MAX_UPLOAD_SIZE").ok().and_then(|v| v.parse().ok()).unwrap_or(10 * 1024 * 1024 * 1024)
+}
+
+pub enum CreateError {
+    TooLarge,
+    Io(io::Error),
+}
+
+/// Starts a resumable upload session for `dest_path` (already home-relative
+/// and joined), expecting `total` bytes overall. Returns the opaque session id
+/// a client uses for subsequent `PATCH`/`HEAD` requests.
+pub fn create_session(home: &PathBuf, dest_path: PathBuf, total: u64) -> Result<String, CreateError> {
+    if total > max_upload_size() {
+        return Err(CreateError::TooLarge);
+    }
+
+    let session_id = Uuid::new_v4().to_string();
+    let temp_path = home.join(SESSION_DIR).join(&session_id);
+
+    if let Some(parent) = temp_path.parent() {
+        fs::create_dir_all(parent).map_err(CreateError::Io)?;
+    }
+    File::create(&temp_path).map_err(CreateError::Io)?;
+
+    SESSIONS.lock().unwrap().insert(session_id.clone(), Session { temp_path, dest_path, total });
+    Ok(session_id)
+}
+
+pub enum PatchOutcome {
+    Continue(u64),
+    Completed,
+}
+
+pub enum PatchError {
+    NotFound,
+    OffsetMismatch(u64),
+    TooLarge,
+    Io(io::Error),
+}
+
+/// Appends `chunk` to the session's temp file at `offset`, rejecting a chunk
+/// that doesn't start where the temp file currently ends. Once the temp file
+/// reaches the session's expected total, it's atomically renamed into place
+/// and the session is dropped.
+pub fn apply_chunk(session_id: &str, offset: u64, chunk: &mut impl io::Read) -> Result<PatchOutcome, PatchError> {
+    let (temp_path, dest_path, total) = {
+        let sessions = SESSIONS.lock().unwrap();
+        let session = sessions.get(session_id).ok_or(PatchError::NotFound)?;
+        (session.temp_path.clone(), session.dest_path.clone(), session.total)
+    };
+
+    let current_len = fs::metadata(&temp_path).map_err(PatchError::Io)?.len();
+    if current_len != offset {
+        return Err(PatchError::OffsetMismatch(current_len));
+    }
+
+    let mut file = OpenOptions::new().append(true).open(&temp_path).map_err(PatchError::Io)?;
+    let mut bounded = chunk.take(total - offset);
+    io::copy(&mut bounded, &mut file).map_err(PatchError::Io)?;
+
+    // The chunk may declare more bytes than are left in the session; if the
+    // caller still has data after we stopped reading at `total`, the chunk
+    // was oversized and the upload must be rejected rather than truncated,
+    // so the part of it already written is dropped again.
+    let mut trailing = [0u8; 1];
+    if bounded.into_inner().read(&mut trailing).map_err(PatchError::Io)? > 0 {
+        file.set_len(offset).map_err(PatchError::Io)?;
+        return Err(PatchError::TooLarge);
+    }
+
+    let new_len = fs::metadata(&temp_path).map_err(PatchError::Io)?.len();
+    if new_len >= total {
+        SESSIONS.lock().unwrap().remove(session_id);
+        fs::rename(&temp_path, &dest_path).map_err(PatchError::Io)?;
+        Ok(PatchOutcome::Completed)
+    } else {
+        Ok(PatchOutcome::Continue(new_len))
+    }
+}
+
+/// Current byte offset of an in-progress session, for `HEAD` polling after a
+/// dropped connection.
+pub fn session_offset(session_id: &str) -> Option<u64> {
+    let temp_path = SESSIONS.lock().unwrap().get(session_id)?.temp_path.clone();
+    fs::metadata(temp_path).ok().map(|m| m.len())
+}
@@ -0,0 +1,299 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId};
+use russh_keys::key;
+use russh_sftp::protocol::{
+    Attrs, Data as SftpData, File as SftpFile, FileAttributes, Handle, Name, OpenFlags, StatusCode, Version,
+};
+
+use crate::auth;
+use crate::backend::{Backend, HomeDirBackend};
+
+/// Launches the embedded SFTP server on its own thread/runtime so it can run
+/// alongside the synchronous Rocket server started in `main`.
+pub fn spawn(bind: &str) {
+    let bind = bind.to_string();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().expect("failed to start SFTP runtime");
+        runtime.block_on(run(bind));
+    });
+}
+
+async fn run(bind: String) {
+    let mut config = server::Config::default();
+    config.keys.push(key::KeyPair::generate_ed25519().expect("failed to generate SFTP host key"));
+    let config = Arc::new(config);
+
+    if let Err(err) = server::run(config, &bind, SshServer).await {
+        eprintln!("SFTP server stopped: {}", err);
+    }
+}
+
+struct SshServer;
+
+impl server::Server for SshServer {
+    type Handler = SshSession;
+
+    fn new_client(&mut self, _: Option<std::net::SocketAddr>) -> SshSession {
+        SshSession
+    }
+}
+
+struct SshSession;
+
+#[async_trait]
+impl server::Handler for SshSession {
+    type Error = russh::Error;
+
+    async fn auth_none(self, _user: &str) -> Result<(Self, Auth), Self::Error> {
+        Ok((self, Auth::reject()))
+    }
+
+    /// Checks the same username/password store the HTTP side's Basic auth
+    /// uses, so SFTP can't be used to bypass the login the web routes require.
+    async fn auth_password(self, user: &str, password: &str) -> Result<(Self, Auth), Self::Error> {
+        if auth::verify_credentials(user, password) {
+            Ok((self, Auth::Accept))
+        } else {
+            Ok((self, Auth::reject()))
+        }
+    }
+
+    async fn channel_open_session(self, _channel: Channel<Msg>, session: Session) -> Result<(Self, bool, Session), Self::Error> {
+        Ok((self, true, session))
+    }
+
+    async fn subsystem_request(self, channel_id: ChannelId, name: &str, mut session: Session) -> Result<(Self, Session), Self::Error> {
+        if name == "sftp" {
+            let backend = HomeDirBackend::new().map_err(|_| russh::Error::Disconnect)?;
+            let sftp = SftpSession::new(Arc::new(backend));
+            session.channel_success(channel_id);
+            russh_sftp::server::run(session.handle(), channel_id, sftp).await;
+        } else {
+            session.channel_failure(channel_id);
+        }
+
+        Ok((self, session))
+    }
+}
+
+enum OpenHandle {
+    Read(std::fs::File),
+    Write(std::fs::File),
+    Dir(Vec<crate::backend::EntryInfo>),
+}
+
+/// Translates the SFTP protocol onto `Backend`, so the file view exposed over
+/// SSH matches the one the web routes serve.
+struct SftpSession {
+    backend: Arc<dyn Backend>,
+    handles: HashMap<String, OpenHandle>,
+    next_handle: AtomicU32,
+}
+
+impl SftpSession {
+    fn new(backend: Arc<dyn Backend>) -> Self {
+        SftpSession { backend, handles: HashMap::new(), next_handle: AtomicU32::new(0) }
+    }
+
+    fn alloc_handle(&self) -> String {
+        self.next_handle.fetch_add(1, Ordering::Relaxed).to_string()
+    }
+}
+
+/// Maps an IO error (or a path-jail violation) onto the closest SFTP status code.
+fn to_status(err: io::Error) -> StatusCode {
+    match err.kind() {
+        io::ErrorKind::NotFound => StatusCode::NoSuchFile,
+        io::ErrorKind::PermissionDenied => StatusCode::PermissionDenied,
+        _ => StatusCode::Failure,
+    }
+}
+
+/// Checks `path` against `access_rules.json` before the backend ever touches
+/// the filesystem, so an authenticated SFTP session still can't read/write
+/// paths the HTTP side's access rules mark off-limits.
+fn check_access(path: &str, access: auth::Access) -> Result<(), StatusCode> {
+    // Rules match `/`-rooted paths, so bring relative and `.`/`..` forms into
+    // that shape first; otherwise `secret/x` or `/a/../secret/x` would slip
+    // past a `/secret` rule.
+    let mut parts: Vec<String> = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            std::path::Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            std::path::Component::ParentDir => { parts.pop(); }
+            _ => {}
+        }
+    }
+
+    if auth::path_allows(&format!("/{}", parts.join("/")), access) {
+        Ok(())
+    } else {
+        Err(StatusCode::PermissionDenied)
+    }
+}
+
+fn to_attrs(entry: &crate::backend::EntryInfo) -> FileAttributes {
+    let mut attrs = FileAttributes::default();
+    attrs.size = Some(entry.size);
+    attrs.mtime = entry.modified.and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok()).map(|d| d.as_secs() as u32);
+    attrs
+}
+
+#[async_trait]
+impl russh_sftp::protocol::Handler for SftpSession {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported
+    }
+
+    async fn init(&mut self, _version: u32, _extensions: HashMap<String, String>) -> Result<Version, Self::Error> {
+        Ok(Version::new())
+    }
+
+    async fn open(&mut self, id: u32, filename: String, pflags: OpenFlags, _attrs: FileAttributes) -> Result<Handle, Self::Error> {
+        let path = Path::new(&filename);
+        let handle_id = self.alloc_handle();
+
+        if pflags.contains(OpenFlags::WRITE) || pflags.contains(OpenFlags::CREATE) {
+            check_access(&filename, auth::Access::Write)?;
+            let create = pflags.contains(OpenFlags::CREATE);
+            let append = pflags.contains(OpenFlags::APPEND);
+            let truncate = pflags.contains(OpenFlags::TRUNCATE);
+            let file = self.backend.open_write(path, create, append, truncate).map_err(to_status)?;
+            self.handles.insert(handle_id.clone(), OpenHandle::Write(file));
+        } else {
+            check_access(&filename, auth::Access::Read)?;
+            let file = self.backend.open_read(path).map_err(to_status)?;
+            self.handles.insert(handle_id.clone(), OpenHandle::Read(file));
+        }
+
+        Ok(Handle { id, handle: handle_id })
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<StatusCode, Self::Error> {
+        self.handles.remove(&handle);
+        let _ = id;
+        Ok(StatusCode::Ok)
+    }
+
+    async fn read(&mut self, id: u32, handle: String, offset: u64, len: u32) -> Result<SftpData, Self::Error> {
+        let file = match self.handles.get_mut(&handle) {
+            Some(OpenHandle::Read(file)) => file,
+            Some(OpenHandle::Write(file)) => file,
+            _ => return Err(StatusCode::Failure),
+        };
+
+        file.seek(SeekFrom::Start(offset)).map_err(to_status)?;
+        let mut buf = vec![0u8; len as usize];
+        let read = file.read(&mut buf).map_err(to_status)?;
+        if read == 0 {
+            return Err(StatusCode::Eof);
+        }
+        buf.truncate(read);
+
+        Ok(SftpData { id, data: buf })
+    }
+
+    async fn write(&mut self, id: u32, handle: String, offset: u64, data: Vec<u8>) -> Result<StatusCode, Self::Error> {
+        let file = match self.handles.get_mut(&handle) {
+            Some(OpenHandle::Write(file)) => file,
+            _ => return Err(StatusCode::Failure),
+        };
+
+        file.seek(SeekFrom::Start(offset)).map_err(to_status)?;
+        file.write_all(&data).map_err(to_status)?;
+        let _ = id;
+        Ok(StatusCode::Ok)
+    }
+
+    async fn lstat(&mut self, id: u32, path: String) -> Result<SftpFile, Self::Error> {
+        check_access(&path, auth::Access::Read)?;
+        let entry = self.backend.stat(Path::new(&path)).map_err(to_status)?;
+        Ok(SftpFile { id, filename: entry.name.clone(), attrs: to_attrs(&entry) })
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<SftpFile, Self::Error> {
+        self.lstat(id, path).await
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let entry = match self.handles.get(&handle) {
+            Some(OpenHandle::Read(file)) | Some(OpenHandle::Write(file)) => {
+                let metadata = file.metadata().map_err(to_status)?;
+                crate::backend::EntryInfo {
+                    name: String::new(),
+                    is_dir: metadata.is_dir(),
+                    size: metadata.len(),
+                    modified: metadata.modified().ok(),
+                }
+            }
+            _ => return Err(StatusCode::Failure),
+        };
+
+        Ok(Attrs { id, attrs: to_attrs(&entry) })
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+        check_access(&path, auth::Access::Read)?;
+        let entries = self.backend.list_dir(Path::new(&path)).map_err(to_status)?;
+        let handle_id = self.alloc_handle();
+        self.handles.insert(handle_id.clone(), OpenHandle::Dir(entries));
+        Ok(Handle { id, handle: handle_id })
+    }
+
+    async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+        match self.handles.get_mut(&handle) {
+            Some(OpenHandle::Dir(entries)) if !entries.is_empty() => {
+                let files = entries.drain(..).map(|entry| SftpFile {
+                    id,
+                    filename: entry.name.clone(),
+                    attrs: to_attrs(&entry),
+                }).collect();
+                Ok(Name { id, files })
+            }
+            Some(OpenHandle::Dir(_)) => Err(StatusCode::Eof),
+            _ => Err(StatusCode::Failure),
+        }
+    }
+
+    async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+        let resolved = self.backend.resolve(Path::new(&path)).map_err(to_status)?;
+        Ok(Name {
+            id,
+            files: vec![SftpFile {
+                id,
+                filename: resolved.to_string_lossy().into_owned(),
+                attrs: FileAttributes::default(),
+            }],
+        })
+    }
+
+    async fn remove(&mut self, id: u32, path: String) -> Result<StatusCode, Self::Error> {
+        check_access(&path, auth::Access::Write)?;
+        self.backend.remove_file(Path::new(&path)).map_err(to_status)?;
+        let _ = id;
+        Ok(StatusCode::Ok)
+    }
+
+    async fn mkdir(&mut self, id: u32, path: String, _attrs: FileAttributes) -> Result<StatusCode, Self::Error> {
+        check_access(&path, auth::Access::Write)?;
+        self.backend.create_dir(Path::new(&path)).map_err(to_status)?;
+        let _ = id;
+        Ok(StatusCode::Ok)
+    }
+
+    async fn rmdir(&mut self, id: u32, path: String) -> Result<StatusCode, Self::Error> {
+        check_access(&path, auth::Access::Write)?;
+        self.backend.remove_dir(Path::new(&path)).map_err(to_status)?;
+        let _ = id;
+        Ok(StatusCode::Ok)
+    }
+}
@@ -0,0 +1,49 @@
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Compares two strings the way a human would order filenames, treating runs
+/// of digits as numbers instead of comparing them byte-by-byte, so `file2`
+/// sorts before `file10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(&ac), Some(&bc)) if ac.is_ascii_digit() && bc.is_ascii_digit() => {
+                match take_number(&mut a_chars).cmp(&take_number(&mut b_chars)) {
+                    Ordering::Equal => continue,
+                    other => return other,
+                }
+            }
+            (Some(&ac), Some(&bc)) => match ac.cmp(&bc) {
+                Ordering::Equal => {
+                    a_chars.next();
+                    b_chars.next();
+                }
+                other => return other,
+            },
+        }
+    }
+}
+
+/// Consumes a run of digits and folds it into a `u64`, saturating instead of
+/// overflowing on a pathologically long run (e.g. a filename with 25+ digits)
+/// so such names just sort as very large rather than panicking/wrapping.
+fn take_number(chars: &mut Peekable<Chars>) -> u64 {
+    let mut n: u64 = 0;
+    while let Some(&c) = chars.peek() {
+        match c.to_digit(10) {
+            Some(d) => {
+                n = n.saturating_mul(10).saturating_add(d as u64);
+                chars.next();
+            }
+            None => break,
+        }
+    }
+    n
+}
@@ -0,0 +1,37 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use minijinja::Environment;
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+const INDEX_TEMPLATE_NAME: &str = "index.html";
+const TEMPLATE_OVERRIDE_DIR: &str = "templates";
+const DEFAULT_INDEX_TEMPLATE: &str = include_str!("../templates/index.html.jinja");
+
+/// Loads the index template source, preferring an override in `templates/`
+/// (discovered relative to the working directory at startup) over the
+/// template embedded in the binary.
+fn load_index_source() -> String {
+    std::fs::read_to_string(Path::new(TEMPLATE_OVERRIDE_DIR).join(format!("{}.jinja", INDEX_TEMPLATE_NAME)))
+        .unwrap_or_else(|_| DEFAULT_INDEX_TEMPLATE.to_string())
+}
+
+fn build_environment() -> Environment<'static> {
+    let mut env = Environment::new();
+    env.add_template_owned(INDEX_TEMPLATE_NAME, load_index_source()).expect("index template failed to parse");
+    env
+}
+
+static ENVIRONMENT: Lazy<Mutex<Environment<'static>>> = Lazy::new(|| Mutex::new(build_environment()));
+
+/// Renders the directory-listing template against `context`. In debug builds
+/// the template is reloaded from `templates/` on every call so overrides can
+/// be edited without restarting the server.
+pub fn render_index(context: &impl Serialize) -> String {
+    if cfg!(debug_assertions) {
+        *ENVIRONMENT.lock().unwrap() = build_environment();
+    }
+
+    ENVIRONMENT.lock().unwrap().get_template(INDEX_TEMPLATE_NAME).unwrap().render(context).unwrap()
+}
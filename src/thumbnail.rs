@@ -0,0 +1,91 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const THUMBNAIL_DIR: &str = ".thumbnails";
+const METADATA_DIR: &str = ".metadata";
+const THUMBNAIL_MAX_SIDE: u32 = 256;
+
+/// Sidecar cached alongside a generated thumbnail, used to decide whether the
+/// source file has changed since the thumbnail was last generated.
+#[derive(Serialize, Deserialize)]
+pub struct Metadata {
+    pub size: u64,
+    pub modified: u64,
+    pub file_type: String,
+    pub sha256_hash: String,
+}
+
+/// Whether `path`'s extension is one we know how to thumbnail.
+pub fn is_image(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref(),
+        Some("png") | Some("jpg") | Some("jpeg") | Some("gif") | Some("bmp") | Some("webp")
+    )
+}
+
+fn mime_for(path: &Path) -> String {
+    match path.extension().and_then(|ext| ext.to_str()).map(|ext| ext.to_lowercase()).as_deref() {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("bmp") => "image/bmp",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }.into()
+}
+
+fn modified_secs(path: &Path) -> io::Result<u64> {
+    let modified = fs::metadata(path)?.modified()?;
+    Ok(modified.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs())
+}
+
+fn other_err(err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+/// Returns the path of a cached (or freshly generated) thumbnail for the image
+/// at `path`, reusing the existing thumbnail/metadata pair when `path`'s mtime
+/// hasn't changed since they were written.
+pub fn ensure_thumbnail(home: &Path, path: &Path) -> io::Result<PathBuf> {
+    let relpath = path.strip_prefix(home).unwrap_or(path);
+    let thumb_path = home.join(THUMBNAIL_DIR).join(relpath);
+    let meta_path = home.join(METADATA_DIR).join(format!("{}.json", relpath.display()));
+
+    let source_modified = modified_secs(path)?;
+
+    if thumb_path.is_file() {
+        if let Ok(existing) = fs::read_to_string(&meta_path) {
+            if let Ok(meta) = serde_json::from_str::<Metadata>(&existing) {
+                if meta.modified == source_modified {
+                    return Ok(thumb_path);
+                }
+            }
+        }
+    }
+
+    if let Some(parent) = thumb_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Some(parent) = meta_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let img = image::open(path).map_err(other_err)?;
+    img.thumbnail(THUMBNAIL_MAX_SIDE, THUMBNAIL_MAX_SIDE).save(&thumb_path).map_err(other_err)?;
+
+    let source_bytes = fs::read(path)?;
+    let metadata = Metadata {
+        size: source_bytes.len() as u64,
+        modified: source_modified,
+        file_type: mime_for(path),
+        sha256_hash: format!("{:x}", Sha256::digest(&source_bytes)),
+    };
+    fs::write(&meta_path, serde_json::to_string(&metadata).map_err(other_err)?)?;
+
+    Ok(thumb_path)
+}